@@ -19,6 +19,8 @@ extern crate libc;
 #[macro_use]
 extern crate lazy_static;
 
+mod md5;
+
 use std::str;
 use std::collections::HashSet;
 
@@ -38,18 +40,232 @@ struct RustExample {
 
     // variables related to SSL/TLS
     tls_sni: String,
+    tls_version: TlsVersion,
+    tls_ja3_str: String,
+    tls_ja3_hash: String,
+
+    // ALPN protocols offered by the client, deduplicated across retransmits
+    tls_alpn: HashSet<String>,
+
+    // 1 if an outer Encrypted Client Hello was seen (tls_sni is then only the
+    // decoy public_name, not the real destination), 0 otherwise
+    tls_ech: u8,
+
+    // variables related to the server-side handshake (ServerHello)
+    tls_cipher: u16,
+    tls_ja3s_hash: String,
+
+    // reassembly state for handshakes split across TCP segments and/or TLS records
+    tls_client: TlsReassembly,
+    tls_server: TlsReassembly,
+}
+
+
+//  ------------  JA3 client fingerprint  ------------
+
+/// Fields accumulated while walking a ClientHello, used to build the JA3 fingerprint.
+struct Ja3Fields {
+    version: u16,
+    ciphers: Vec<u16>,
+    extensions: Vec<u16>,
+    groups: Vec<u16>,
+    ec_point_formats: Vec<u8>,
+}
+
+impl Ja3Fields {
+    fn new() -> Ja3Fields {
+        Ja3Fields {
+            version: 0,
+            ciphers: Vec::new(),
+            extensions: Vec::new(),
+            groups: Vec::new(),
+            ec_point_formats: Vec::new(),
+        }
+    }
+
+    /// Builds the `version,ciphers,extensions,groups,ec_point_formats` string
+    /// that gets MD5-hashed into the JA3 fingerprint.
+    fn to_ja3_string(&self) -> String {
+        let join = |vals: &[u16]| vals.iter().map(|v| v.to_string())
+                                       .collect::<Vec<String>>().join("-");
+        let formats = self.ec_point_formats.iter().map(|v| v.to_string())
+                                            .collect::<Vec<String>>().join("-");
+        format!("{},{},{},{},{}", self.version, join(&self.ciphers), join(&self.extensions),
+                join(&self.groups), formats)
+    }
+}
+
+/// Checks whether a 16-bit value is a GREASE value (RFC 8701).
+fn is_grease_u16(val: u16) -> bool {
+    (val & 0x0f0f) == 0x0a0a && (val >> 8) == (val & 0x00ff)
+}
+
+// TLS record/handshake type constants shared by the extraction and
+// reassembly logic.
+const TLS_HANDSHAKE: u8 = 22;
+const TLS_CLIENT_HELLO: u8 = 1;
+const TLS_SERVER_HELLO: u8 = 2;
+
+/// Cap on bytes buffered per side while reassembling a TLS handshake.
+const MAX_TLS_REASSEMBLY: usize = 16 * 1024;
+
+/// Reads the TCP sequence number and (source, destination) ports out of the
+/// packet's L4 header, used to validate reassembly continuations.
+fn tcp_seq_and_ports(packet: &Packet) -> Option<(u32, u16, u16)> {
+    let hdr = packet.l4_header(); // mirrors l7_header(); unverified against t2plugin itself
+    if hdr.len() < 8 {
+        return None;
+    }
+    let src_port = u16::from_be_bytes([hdr[0], hdr[1]]);
+    let dst_port = u16::from_be_bytes([hdr[2], hdr[3]]);
+    let seq = u32::from_be_bytes([hdr[4], hdr[5], hdr[6], hdr[7]]);
+    Some((seq, src_port, dst_port))
+}
+
+/// Per-side TLS handshake reassembly state, normalized to look like a single TLS record.
+struct TlsReassembly {
+    buf: Vec<u8>,
+    ports: Option<(u16, u16)>,
+    next_seq: Option<u32>,
+    record_remaining: usize,
+}
+
+impl TlsReassembly {
+    fn new() -> TlsReassembly {
+        TlsReassembly { buf: Vec::new(), ports: None, next_seq: None, record_remaining: 0 }
+    }
+
+    fn reset(&mut self) {
+        self.buf.clear();
+        self.ports = None;
+        self.next_seq = None;
+        self.record_remaining = 0;
+    }
+
+    // Strips record framing out of `payload` and appends the handshake bytes
+    // to `buf`, stopping once the first handshake message is complete, at
+    // the cap, or at a record that is not a handshake continuation. Returns
+    // how many bytes of `payload` were consumed.
+    fn feed(&mut self, mut payload: &[u8], want_handshake_type: u8) -> usize {
+        let start_len = payload.len();
+        while !payload.is_empty() && self.buf.len() < MAX_TLS_REASSEMBLY
+              && !handshake_message_ready(&self.buf) {
+            if self.record_remaining == 0 {
+                if payload.len() <= 5 {
+                    break; // record header split across segments: not buffered, reassembly stops here
+                }
+                let record_type = payload[0];
+                let declared = ((payload[3] as usize) << 8) | payload[4] as usize;
+                if self.buf.is_empty() {
+                    if record_type != TLS_HANDSHAKE || payload[5] != want_handshake_type {
+                        break; // not the handshake we are waiting for
+                    }
+                    self.buf.extend_from_slice(&payload[.. 5]); // length patched below
+                } else if record_type != TLS_HANDSHAKE {
+                    break; // not a handshake continuation
+                }
+                self.record_remaining = declared;
+                payload = &payload[5 ..];
+            } else {
+                let mut take = payload.len().min(self.record_remaining)
+                                            .min(MAX_TLS_REASSEMBLY - self.buf.len());
+                if let Some(needed) = handshake_total_needed(&self.buf) {
+                    take = take.min(needed - self.buf.len()); // don't pull in the next handshake message
+                }
+                if take == 0 {
+                    break;
+                }
+                self.buf.extend_from_slice(&payload[.. take]);
+                self.record_remaining -= take;
+                payload = &payload[take ..];
+            }
+        }
+        if self.buf.len() >= 5 {
+            let len = (self.buf.len() - 5) as u16;
+            self.buf[3] = (len >> 8) as u8;
+            self.buf[4] = (len & 0xff) as u8;
+        }
+        start_len - payload.len()
+    }
+}
+
+/// Total bytes (handshake header + body) the first handshake message in a
+/// `TlsReassembly` buffer needs, once its 4-byte header has been buffered.
+fn handshake_total_needed(buf: &[u8]) -> Option<usize> {
+    if buf.len() < 9 {
+        return None;
+    }
+    let handshake_len = ((buf[6] as usize) << 16) | ((buf[7] as usize) << 8) | buf[8] as usize;
+    Some(9 + handshake_len)
+}
+
+/// Whether a `TlsReassembly` buffer holds a complete handshake message.
+fn handshake_message_ready(buf: &[u8]) -> bool {
+    handshake_total_needed(buf).map_or(false, |needed| buf.len() >= needed)
+}
+
+/// Feeds `payload` into `state` if it belongs to the handshake already being
+/// collected, or starts a fresh collection if `payload` begins one of type
+/// `want_handshake_type`. Segments from the other direction, retransmits and
+/// duplicates, and anything once the handshake message is complete are ignored.
+fn buffer_tls_segment(state: &mut TlsReassembly, payload: &[u8], want_handshake_type: u8,
+                      seq: u32, ports: (u16, u16)) {
+    match state.ports {
+        None => {
+            let consumed = state.feed(payload, want_handshake_type);
+            if consumed > 0 {
+                state.ports = Some(ports);
+                state.next_seq = Some(seq.wrapping_add(consumed as u32));
+            }
+        },
+        Some(owner_ports) => {
+            if ports != owner_ports || state.next_seq != Some(seq) {
+                return; // other direction, or a retransmitted/duplicate segment
+            }
+            if handshake_message_ready(&state.buf) {
+                return;
+            }
+            let consumed = state.feed(payload, want_handshake_type);
+            state.next_seq = Some(seq.wrapping_add(consumed as u32));
+        },
+    }
+}
+
+
+//  ------------  JA3S server fingerprint  ------------
+
+/// Fields accumulated while walking a ServerHello, used to build the JA3S fingerprint.
+struct Ja3sFields {
+    version: u16,
+    cipher: u16,
+    extensions: Vec<u16>,
+}
+
+impl Ja3sFields {
+    fn new() -> Ja3sFields {
+        Ja3sFields { version: 0, cipher: 0, extensions: Vec::new() }
+    }
+
+    /// Builds the `version,cipher,extensions` string that gets MD5-hashed
+    /// into the JA3S fingerprint.
+    fn to_ja3s_string(&self) -> String {
+        let extensions = self.extensions.iter().map(|v| v.to_string())
+                                         .collect::<Vec<String>>().join("-");
+        format!("{},{},{}", self.version, self.cipher, extensions)
+    }
 }
 
 
 //  ------------  Supported TLS versions enum  ------------
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
 enum TlsVersion {
     UNKNOWN,
     SSLv3,
     TLSv1,
     TLSv11,
     TLSv12,
+    TLSv13,
 }
 
 impl TlsVersion {
@@ -59,9 +275,38 @@ impl TlsVersion {
             0x0301 => TlsVersion::TLSv1,
             0x0302 => TlsVersion::TLSv11,
             0x0303 => TlsVersion::TLSv12,
+            0x0304 => TlsVersion::TLSv13,
             _ => TlsVersion::UNKNOWN,
         }
     }
+
+    /// String representation output in the `tlsVersion` column.
+    fn as_str(&self) -> &'static str {
+        match *self {
+            TlsVersion::UNKNOWN => "unknown",
+            TlsVersion::SSLv3 => "SSLv3",
+            TlsVersion::TLSv1 => "TLSv1.0",
+            TlsVersion::TLSv11 => "TLSv1.1",
+            TlsVersion::TLSv12 => "TLSv1.2",
+            TlsVersion::TLSv13 => "TLSv1.3",
+        }
+    }
+
+    /// Ordering rank used to pick the highest of several advertised versions.
+    fn rank(&self) -> u8 {
+        match *self {
+            TlsVersion::UNKNOWN => 0,
+            TlsVersion::SSLv3 => 1,
+            TlsVersion::TLSv1 => 2,
+            TlsVersion::TLSv11 => 3,
+            TlsVersion::TLSv12 => 4,
+            TlsVersion::TLSv13 => 5,
+        }
+    }
+
+    fn is_newer_than(&self, other: TlsVersion) -> bool {
+        self.rank() > other.rank()
+    }
 }
 
 
@@ -114,12 +359,17 @@ fn extract_phpid<'a>(slr: &'a mut SliceReader) -> Option<(bool, &'a str)> {
     None
 }
 
-/// Extract the TLS/SSL SNI value.
-fn extract_tls_sni<'a>(slr: &'a mut SliceReader) -> Option<&'a str> {
+/// Extract the TLS/SSL SNI value and the highest TLS version offered by the client.
+fn extract_tls_sni<'a>(slr: &'a mut SliceReader, version: &mut TlsVersion, ja3: &mut Ja3Fields,
+                       alpn: &mut HashSet<String>, ech: &mut u8) -> Option<&'a str> {
     // TLS related constants
-    const HANDSHAKE: u8 = 22;
-    const CLIENT_HELLO: u8 = 1;
     const SERVER_NAME: u16 = 0x0000;
+    const SUPPORTED_GROUPS: u16 = 0x000a;
+    const EC_POINT_FORMATS: u16 = 0x000b;
+    const ALPN: u16 = 0x0010;
+    const SUPPORTED_VERSIONS: u16 = 0x002b;
+    const ECH: u16 = 0xfe0d;
+    const ECH_OUTER: u8 = 0;
 
     // for each TLS record in the packet
     loop {
@@ -130,7 +380,7 @@ fn extract_tls_sni<'a>(slr: &'a mut SliceReader) -> Option<&'a str> {
             return None;
         }
         let len = tryopt!(slr.read_u16()) as usize;
-        if record_type != HANDSHAKE {
+        if record_type != TLS_HANDSHAKE {
             // not an handshake: skip current record and check next one
             slr.skip(len);
             continue;
@@ -142,46 +392,213 @@ fn extract_tls_sni<'a>(slr: &'a mut SliceReader) -> Option<&'a str> {
         while slr.pos() < handshakes_end {
             let handshake_type = tryopt!(slr.read_u8());
             let len = tryopt!(slr.read_u24()) as usize; // handshake length
-            if handshake_type != CLIENT_HELLO {
+            if handshake_type != TLS_CLIENT_HELLO {
                 // skip current handshake and check next one
                 slr.skip(len);
                 continue;
             }
 
             // check the TLS version requested by the client
-            // might be different from the TLS version used during the handshake
-            if TlsVersion::from_u16(tryopt!(slr.read_u16())) == TlsVersion::UNKNOWN {
+            // might be different from the TLS version used during the handshake;
+            // for TLS 1.3 this is frozen at 0x0303 (TLSv12) and gets overridden
+            // below if a supported_versions extension is present
+            let raw_version = tryopt!(slr.read_u16());
+            let legacy_version = TlsVersion::from_u16(raw_version);
+            if legacy_version == TlsVersion::UNKNOWN {
                 return None; // unsupported TLS version
             }
-            
+            *version = legacy_version;
+            ja3.version = raw_version; // JA3 uses the ClientHello version verbatim
+
             // skip handshake fields we are not interested in
             slr.skip(32); // skip random
             let len = tryopt!(slr.read_u8()) as usize; // session ID length
-            slr.skip(len); // skip session ID
+            slr.skip(len); // skip session ID (32 random bytes in TLS 1.3)
             let len = tryopt!(slr.read_u16()) as usize; // cipher suite length
-            slr.skip(len); // skip cipher suite
+            for _ in 0 .. len / 2 {
+                let cipher = tryopt!(slr.read_u16());
+                if !is_grease_u16(cipher) {
+                    ja3.ciphers.push(cipher);
+                }
+            }
             let len = tryopt!(slr.read_u8()) as usize; // compression methods length
             slr.skip(len); // skip compression methods
 
             let len = tryopt!(slr.read_u16()) as usize; // extensions length
             let extensions_end = slr.pos() + len;
 
+            let mut sni = None;
+
             // for each extension
             while slr.pos() < extensions_end {
                 let extension_type = tryopt!(slr.read_u16());
                 let len = tryopt!(slr.read_u16()) as usize;
-                if extension_type != SERVER_NAME {
-                    // skip current extension and check next one
+                if !is_grease_u16(extension_type) {
+                    ja3.extensions.push(extension_type);
+                }
+                match extension_type {
+                    SERVER_NAME => {
+                        // extract the server name field
+                        slr.skip(3); // skip list length and type
+                        let name_len = tryopt!(slr.read_u16()) as usize; // server name length
+                        let name = tryopt!(slr.read_bytes(name_len));
+                        sni = Some(tryopt!(str::from_utf8(name)));
+                    },
+                    SUPPORTED_GROUPS => {
+                        // elliptic curves offered by the client
+                        let list_len = tryopt!(slr.read_u16()) as usize;
+                        let list_end = slr.pos() + list_len;
+                        while slr.pos() < list_end {
+                            let group = tryopt!(slr.read_u16());
+                            if !is_grease_u16(group) {
+                                ja3.groups.push(group);
+                            }
+                        }
+                    },
+                    EC_POINT_FORMATS => {
+                        // EC point formats offered by the client
+                        let list_len = tryopt!(slr.read_u8()) as usize;
+                        for _ in 0 .. list_len {
+                            ja3.ec_point_formats.push(tryopt!(slr.read_u8()));
+                        }
+                    },
+                    ALPN => {
+                        // application protocols offered by the client
+                        let list_len = tryopt!(slr.read_u16()) as usize;
+                        let list_end = slr.pos() + list_len;
+                        while slr.pos() < list_end {
+                            let name_len = tryopt!(slr.read_u8()) as usize;
+                            let name = tryopt!(slr.read_bytes(name_len));
+                            if let Ok(name) = str::from_utf8(name) {
+                                alpn.insert(name.to_string());
+                            }
+                        }
+                    },
+                    ECH => {
+                        // ECHClientHello: a leading type byte selects the
+                        // outer (public, sent in the clear) or inner (only
+                        // ever seen decrypted) variant
+                        let ech_type = tryopt!(slr.read_u8());
+                        if ech_type == ECH_OUTER {
+                            slr.skip(4); // HpkeKdfId + HpkeAeadId cipher suite
+                            slr.skip(1); // config_id
+                            let enc_len = tryopt!(slr.read_u16()) as usize;
+                            slr.skip(enc_len); // enc
+                            let payload_len = tryopt!(slr.read_u16()) as usize;
+                            slr.skip(payload_len); // payload
+                            *ech = 1;
+                        }
+                    },
+                    SUPPORTED_VERSIONS => {
+                        // client-advertised versions, highest preference first;
+                        // record the highest one offered
+                        let list_len = tryopt!(slr.read_u8()) as usize;
+                        let list_end = slr.pos() + list_len;
+                        while slr.pos() < list_end {
+                            let offered = TlsVersion::from_u16(tryopt!(slr.read_u16()));
+                            if offered.is_newer_than(*version) {
+                                *version = offered;
+                            }
+                        }
+                    },
+                    _ => {
+                        // not an extension we care about: skip it
+                        slr.skip(len);
+                    },
+                }
+            }
+
+            return sni;
+        }
+    }
+}
+
+/// The random value a server fills in when sending a HelloRetryRequest
+/// instead of a real ServerHello (RFC 8446, section 4.1.3).
+const HELLO_RETRY_REQUEST_RANDOM: [u8; 32] = [
+    0xCF, 0x21, 0xAD, 0x74, 0xE5, 0x9A, 0x61, 0x11,
+    0xBE, 0x1D, 0x8C, 0x02, 0x1E, 0x65, 0xB8, 0x91,
+    0xC2, 0xA2, 0x11, 0x16, 0x7A, 0xBB, 0x8C, 0x5E,
+    0x07, 0x9E, 0x09, 0xE2, 0xC8, 0xA8, 0x33, 0x9C,
+];
+
+/// Extract the negotiated cipher suite and version from a ServerHello, building its JA3S fingerprint.
+fn extract_tls_server_info(slr: &mut SliceReader, version: &mut TlsVersion, cipher: &mut u16,
+                           ja3s: &mut Ja3sFields) -> Option<()> {
+    // TLS related constants
+    const SUPPORTED_VERSIONS: u16 = 0x002b;
+
+    // for each TLS record in the packet
+    loop {
+        let record_type = tryopt!(slr.read_u8());
+        if TlsVersion::from_u16(tryopt!(slr.read_u16())) == TlsVersion::UNKNOWN {
+            return None;
+        }
+        let len = tryopt!(slr.read_u16()) as usize;
+        if record_type != TLS_HANDSHAKE {
+            // not an handshake: skip current record and check next one
+            slr.skip(len);
+            continue;
+        }
+
+        let handshakes_end = slr.pos() + len;
+
+        // for each handshake (usually only one)
+        while slr.pos() < handshakes_end {
+            let handshake_type = tryopt!(slr.read_u8());
+            let len = tryopt!(slr.read_u24()) as usize; // handshake length
+            if handshake_type != TLS_SERVER_HELLO {
+                // skip current handshake and check next one
+                slr.skip(len);
+                continue;
+            }
+
+            let raw_version = tryopt!(slr.read_u16()); // legacy_version
+            let legacy_version = TlsVersion::from_u16(raw_version);
+            if legacy_version == TlsVersion::UNKNOWN {
+                return None; // unsupported TLS version
+            }
+
+            let random = tryopt!(slr.read_bytes(32));
+            let is_hello_retry_request = random == &HELLO_RETRY_REQUEST_RANDOM[..];
+
+            let len = tryopt!(slr.read_u8()) as usize; // session ID length
+            slr.skip(len); // skip session ID
+            let cipher_suite = tryopt!(slr.read_u16());
+            slr.skip(1); // skip compression method
+
+            let len = tryopt!(slr.read_u16()) as usize; // extensions length
+            let extensions_end = slr.pos() + len;
+
+            let mut selected_version = legacy_version;
+
+            // for each extension
+            while slr.pos() < extensions_end {
+                let extension_type = tryopt!(slr.read_u16());
+                let len = tryopt!(slr.read_u16()) as usize;
+                if !is_grease_u16(extension_type) {
+                    ja3s.extensions.push(extension_type);
+                }
+                if extension_type == SUPPORTED_VERSIONS {
+                    // unlike in the ClientHello, here this carries a single
+                    // selected version, not a list: this is how TLS 1.3 is
+                    // actually signaled, since legacy_version stays 0x0303
+                    selected_version = TlsVersion::from_u16(tryopt!(slr.read_u16()));
+                } else {
                     slr.skip(len);
-                    continue;
                 }
+            }
 
-                // this is a server-name extension: extract and return the server name field
-                slr.skip(3); // skip list length and type
-                let len = tryopt!(slr.read_u16()) as usize; // server name length
-                let name = tryopt!(slr.read_bytes(len));
-                return Some(tryopt!(str::from_utf8(name)));
+            if is_hello_retry_request {
+                // not the final negotiation: wait for the real ServerHello
+                return None;
             }
+
+            *version = selected_version;
+            *cipher = cipher_suite;
+            ja3s.version = raw_version;
+            ja3s.cipher = cipher_suite;
+            return Some(());
         }
     }
 }
@@ -195,6 +612,15 @@ impl T2Plugin for RustExample {
             byte_count: 0,
             php_ids: HashSet::new(),
             tls_sni: String::new(),
+            tls_version: TlsVersion::UNKNOWN,
+            tls_ja3_str: String::new(),
+            tls_ja3_hash: String::new(),
+            tls_alpn: HashSet::new(),
+            tls_ech: 0,
+            tls_cipher: 0,
+            tls_ja3s_hash: String::new(),
+            tls_client: TlsReassembly::new(),
+            tls_server: TlsReassembly::new(),
         }
     }
 
@@ -213,6 +639,27 @@ impl T2Plugin for RustExample {
         // 3rd column: TLS SNI: non-repetitive string
         header.add_simple_col("TLS SNI", "tlsSni", false, BinaryType::bt_string);
 
+        // 4th column: negotiated TLS version: non-repetitive string
+        header.add_simple_col("TLS version", "tlsVersion", false, BinaryType::bt_string);
+
+        // 5th column: JA3 client fingerprint hash: non-repetitive string
+        header.add_simple_col("JA3 client fingerprint hash", "ja3Hash", false, BinaryType::bt_string);
+
+        // 6th column: raw JA3 string used to compute the hash: non-repetitive string
+        header.add_simple_col("JA3 client fingerprint string", "ja3Str", false, BinaryType::bt_string);
+
+        // 7th column: ALPN protocols offered by the client: repetitive string
+        header.add_simple_col("TLS ALPN protocols", "tlsAlpn", true, BinaryType::bt_string);
+
+        // 8th column: outer Encrypted Client Hello present: non-repetitive u8
+        header.add_simple_col("TLS ECH outer present", "tlsEch", false, BinaryType::bt_uint_8);
+
+        // 9th column: negotiated cipher suite: non-repetitive u16
+        header.add_simple_col("TLS negotiated cipher suite", "tlsCipher", false, BinaryType::bt_uint_16);
+
+        // 10th column: JA3S server fingerprint hash: non-repetitive string
+        header.add_simple_col("JA3S server fingerprint hash", "ja3sHash", false, BinaryType::bt_string);
+
         header
     }
 
@@ -223,21 +670,65 @@ impl T2Plugin for RustExample {
 
         // process payload of TCP packets
         if packet.snap_l7_len > 0 && packet.l4_type == L4Type::TCP as u8 {
-            let mut slr = SliceReader::new(packet.l7_header());
+            let payload = packet.l7_header();
+            let mut slr = SliceReader::new(payload);
 
             // extract the PHPSESSID cookie
             if let Some((set_cookie, phpid)) = extract_phpid(&mut slr) {
                 self.php_ids.insert((set_cookie, phpid.to_string()));
             }
 
-            // revert slice reader at payload start
-            let pos = slr.pos();
-            slr.rewind(pos).unwrap();
+            // feed the TLS handshake reassembly state, validating continuations against the stream that started it
+            if let Some((seq, src_port, dst_port)) = tcp_seq_and_ports(packet) {
+                let ports = (src_port, dst_port);
+                if self.tls_sni.len() == 0 {
+                    buffer_tls_segment(&mut self.tls_client, payload, TLS_CLIENT_HELLO, seq, ports);
+                }
+                if self.tls_ja3s_hash.len() == 0 {
+                    buffer_tls_segment(&mut self.tls_server, payload, TLS_SERVER_HELLO, seq, ports);
+                }
+            }
 
             // extract the SSL/TLS SNI (server name identification) extension
-            if self.tls_sni.len() == 0 {
-                if let Some(sni) = extract_tls_sni(&mut slr) {
+            // and the JA3 client fingerprint, once the ClientHello is fully
+            // reassembled
+            if self.tls_sni.len() == 0 && handshake_message_ready(&self.tls_client.buf) {
+                let mut slr = SliceReader::new(&self.tls_client.buf);
+                let mut version = TlsVersion::UNKNOWN;
+                let mut ja3 = Ja3Fields::new();
+                let mut ech = 0u8;
+                if let Some(sni) = extract_tls_sni(&mut slr, &mut version, &mut ja3,
+                                                   &mut self.tls_alpn, &mut ech) {
                     self.tls_sni = sni.to_string();
+                    self.tls_version = version;
+                    self.tls_ja3_str = ja3.to_ja3_string();
+                    self.tls_ja3_hash = md5::digest_hex(self.tls_ja3_str.as_bytes());
+                    self.tls_ech = ech;
+                } else {
+                    // no SNI (unsupported TLS version, no server_name extension
+                    // or a malformed ClientHello): version, JA3 and ECH are not
+                    // recorded and the buffer is dropped
+                    self.tls_client.reset();
+                }
+            }
+
+            // extract the negotiated cipher suite and JA3S fingerprint from
+            // the ServerHello (overrides tls_version with the actually
+            // negotiated version, which the ClientHello can only offer),
+            // once it is fully reassembled
+            if self.tls_ja3s_hash.len() == 0 && handshake_message_ready(&self.tls_server.buf) {
+                let mut slr = SliceReader::new(&self.tls_server.buf);
+                let mut version = self.tls_version;
+                let mut cipher = 0u16;
+                let mut ja3s = Ja3sFields::new();
+                if extract_tls_server_info(&mut slr, &mut version, &mut cipher, &mut ja3s).is_some() {
+                    self.tls_version = version;
+                    self.tls_cipher = cipher;
+                    self.tls_ja3s_hash = md5::digest_hex(ja3s.to_ja3s_string().as_bytes());
+                } else {
+                    // HelloRetryRequest or an unsupported version: reset so
+                    // a following real ServerHello gets a fresh buffer
+                    self.tls_server.reset();
                 }
             }
         }
@@ -264,6 +755,32 @@ impl T2Plugin for RustExample {
 
         // 3rd column: output TLS SNI
         output_string(&self.tls_sni);
+
+        // 4th column: output negotiated TLS version
+        output_string(self.tls_version.as_str());
+
+        // 5th column: output JA3 client fingerprint hash
+        output_string(&self.tls_ja3_hash);
+
+        // 6th column: output raw JA3 client fingerprint string
+        output_string(&self.tls_ja3_str);
+
+        // 7th column: output ALPN protocols: repetitive string
+        let tls_alpn: Vec<String> = self.tls_alpn.drain().collect();
+        // repetitive values are prefixed by the number of repetitions as u32
+        output_num(tls_alpn.len() as u32);
+        for alpn in tls_alpn {
+            output_string(alpn);
+        }
+
+        // 8th column: output whether an outer ECH was present
+        output_num(self.tls_ech);
+
+        // 9th column: output negotiated cipher suite
+        output_num(self.tls_cipher);
+
+        // 10th column: output JA3S server fingerprint hash
+        output_string(&self.tls_ja3s_hash);
     }
 }
 